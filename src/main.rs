@@ -1,6 +1,6 @@
 use std::path::Path;
 use std::collections::HashMap;
-use clap::Parser;
+use clap::{Parser, Subcommand, ValueEnum};
 use oort_simulator::simulation::Code;
 use oort_simulator::{scenario, simulation};
 use oort_tools::AI;
@@ -9,11 +9,12 @@ use std::fmt::{Display, Formatter};
 use std::io::BufRead;
 use std::path::PathBuf;
 use std::sync::atomic::AtomicUsize;
-use log::{debug, info, warn};
+use log::{debug, warn};
 use oort_simulator::scenario::Status;
 use oort_simulator::vm::builtin;
-use rayon::iter::{ParallelIterator, IntoParallelRefIterator, IntoParallelIterator};
+use rayon::iter::{ParallelIterator, IntoParallelRefIterator};
 use mimalloc::MiMalloc;
+use serde::Serialize;
 
 #[global_allocator]
 static GLOBAL: MiMalloc = MiMalloc;
@@ -30,9 +31,24 @@ const BRIGHT_YELLOW: &str = "\x1b[93m";
 const BLUE: &str = "\x1b[34m";
 const BRIGHT_BLUE: &str = "\x1b[94m";
 
+#[derive(Parser, Debug)]
+#[clap(name = "oort3_bencher")]
+struct Cli {
+    #[clap(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Head-to-head benchmark of two AIs against each scene's builtin enemy.
+    Benchmark(BenchmarkArgs),
+    /// Round-robin tournament among many AIs, ranked by Elo.
+    Tournament(TournamentArgs),
+}
+
 #[derive(Clone, Parser, Debug)]
 #[clap()]
-struct Arguments {
+struct BenchmarkArgs {
     baseline_shortcode: String,
     new_shortcode: String,
 
@@ -45,32 +61,85 @@ struct Arguments {
     #[clap(long, default_value = "/tmp/oort-wasm-cache")]
     wasm_cache: Option<PathBuf>,
 
+    /// Number of worker threads for the scoped rayon pool driving simulations.
+    #[clap(short, long, default_value_t = num_cpus::get())]
+    threads: usize,
+
+    /// Output format for the final report.
+    #[clap(short, long, value_enum, default_value_t = OutputFormat::Pretty)]
+    format: OutputFormat,
+
+    /// Exit with a nonzero status if any scene regresses beyond the thresholds below
+    /// (and that regression is statistically significant).
+    #[clap(long)]
+    fail_on_regression: bool,
+
+    /// Allowed drop in the new AI's win count before a scene counts as a regression.
+    #[clap(long, default_value_t = 0)]
+    min_win_delta: i32,
+
+    /// Allowed growth in mean `score_time` before a scene counts as a regression.
+    #[clap(long, default_value_t = f64::INFINITY)]
+    max_time_regression: f64,
+
     scene_listing: String
 }
 
-#[derive(Clone, Default, Debug)]
+#[derive(Clone, Parser, Debug)]
+#[clap()]
+struct TournamentArgs {
+    scene_listing: String,
+
+    #[clap(short, long, default_value = "10")]
+    rounds: u32,
+
+    #[clap(long, default_value = "/tmp/oort-wasm-cache")]
+    wasm_cache: Option<PathBuf>,
+
+    /// Number of worker threads for the scoped rayon pool driving simulations.
+    #[clap(short, long, default_value_t = num_cpus::get())]
+    threads: usize,
+
+    /// Output format for the final report.
+    #[clap(short, long, value_enum, default_value_t = OutputFormat::Pretty)]
+    format: OutputFormat,
+
+    /// Paths to AI shortcodes competing in the tournament (at least two).
+    #[clap(required = true, num_args = 2..)]
+    shortcodes: Vec<String>,
+}
+
+#[derive(Clone, Copy, ValueEnum, Debug, PartialEq, Eq)]
+enum OutputFormat {
+    Pretty,
+    Json,
+    Csv,
+}
+
+#[derive(Clone, Default, Debug, Serialize)]
 struct Results {
     team0_wins: Vec<u32>,
     team1_wins: Vec<u32>,
     draws: Vec<u32>,
     times: Vec<f64>,
+    /// Seeds that hit `scenario::MAX_TICKS` (counted as a team1 win above, but
+    /// tracked separately so timeouts don't hide in the raw win/loss counts).
+    timeouts: Vec<u32>,
 }
 
-fn run_simulations(scenario_name: &str, codes: Vec<Code>, rounds: u32) -> Result<Results, String> {
-    let seed_statuses: Vec<(u32, (Status, f64))> = (0..rounds)
-        .into_iter()
-        .map(|seed| (seed, run_simulation(scenario_name, seed, codes.clone())))
-        .collect();
-    info!("Simulation complete");
+fn aggregate_results(seed_statuses: Vec<(u32, Status, f64)>) -> Result<Results, String> {
     let mut results: Results = Default::default();
     debug!("Processing results");
-    for (seed, (status, time)) in seed_statuses {
+    for (seed, status, time) in seed_statuses {
         match status {
             Status::Victory { team: 0 } => results.team0_wins.push(seed),
             Status::Victory { team: 1 } => results.team1_wins.push(seed),
             Status::Victory { team: s } => return Err(format!("Invalid team {}", s)),
             Status::Draw => results.draws.push(seed),
-            Status::Failed => results.team1_wins.push(seed),
+            Status::Failed => {
+                results.team1_wins.push(seed);
+                results.timeouts.push(seed);
+            }
             Status::Running => return Err("Scenario should not be running".to_string()),
         }
         results.times.push(time);
@@ -78,6 +147,58 @@ fn run_simulations(scenario_name: &str, codes: Vec<Code>, rounds: u32) -> Result
     Ok(results)
 }
 
+/// Distribution of a `Results::times` vector beyond the bare mean, so an AI that
+/// usually wins fast but occasionally times out near `MAX_TICKS` doesn't look
+/// identical to a consistent one.
+#[derive(Clone, Debug, Serialize)]
+struct TimingStats {
+    min: f64,
+    median: f64,
+    p90: f64,
+    p95: f64,
+    max: f64,
+    stddev: f64,
+}
+
+fn timing_stats(results: &Results) -> TimingStats {
+    let mut sorted = results.times.clone();
+    sorted.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    let n = sorted.len();
+    if n == 0 {
+        return TimingStats { min: 0.0, median: 0.0, p90: 0.0, p95: 0.0, max: 0.0, stddev: 0.0 };
+    }
+    let percentile = |p: f64| -> f64 {
+        if n == 1 {
+            return sorted[0];
+        }
+        let rank = p * (n as f64 - 1.0);
+        let lo = rank.floor() as usize;
+        let hi = rank.ceil() as usize;
+        sorted[lo] + (sorted[hi] - sorted[lo]) * (rank - lo as f64)
+    };
+    let mean = avg_time(results);
+    let variance = results.times.iter().map(|t| (t - mean).powi(2)).sum::<f64>() / n as f64;
+    TimingStats {
+        min: sorted[0],
+        median: percentile(0.5),
+        p90: percentile(0.90),
+        p95: percentile(0.95),
+        max: sorted[n - 1],
+        stddev: variance.sqrt(),
+    }
+}
+
+/// One unit of flattened work: play a single seed of `scene` with `which` (0 =
+/// baseline, 1 = new) against its scene enemy. Flattening scene/AI/seed into a
+/// single work list (rather than nesting a `par_iter` over scenes around another
+/// over AIs) keeps every core busy even when there is only one scene to run.
+#[derive(Clone, Copy)]
+struct WorkItem {
+    scene: usize,
+    which: usize,
+    seed: u32,
+}
+
 fn run_simulation(scenario_name: &str, seed: u32, codes: Vec<Code>) -> (Status, f64) {
     debug!("Running simulation {scenario_name} at seed {seed}");
     let mut sim = simulation::Simulation::new(scenario_name, seed, &codes);
@@ -91,11 +212,303 @@ fn run_simulation(scenario_name: &str, seed: u32, codes: Vec<Code>) -> (Status,
     (sim.status(), sim.score_time())
 }
 
-#[derive(Clone)]
+// Abramowitz & Stegun 7.1.26, accurate to ~1.5e-7, which is plenty for display purposes.
+fn erf(x: f64) -> f64 {
+    let sign = if x < 0.0 { -1.0 } else { 1.0 };
+    let x = x.abs();
+    let a1 = 0.254829592;
+    let a2 = -0.284496736;
+    let a3 = 1.421413741;
+    let a4 = -1.453152027;
+    let a5 = 1.061405429;
+    let p = 0.3275911;
+    let t = 1.0 / (1.0 + p * x);
+    let y = 1.0 - (((((a5 * t + a4) * t) + a3) * t + a2) * t + a1) * t * (-x * x).exp();
+    sign * y
+}
+
+fn normal_cdf(x: f64) -> f64 {
+    0.5 * (1.0 + erf(x / std::f64::consts::SQRT_2))
+}
+
+/// Two-sided p-value for a two-proportion z-test between `x1`/`n` and `x2`/`n` wins,
+/// along with the z-score. Returns `None` when the pooled proportion makes the
+/// standard error zero (e.g. both sides are all-win or all-loss).
+fn two_proportion_z_test(x1: u32, x2: u32, n: u32) -> Option<(f64, f64)> {
+    let n = n as f64;
+    let p1 = x1 as f64 / n;
+    let p2 = x2 as f64 / n;
+    let pooled = (x1 + x2) as f64 / (2.0 * n);
+    let se = (pooled * (1.0 - pooled) * (2.0 / n)).sqrt();
+    if se == 0.0 {
+        return None;
+    }
+    let z = (p2 - p1) / se;
+    let p_value = 2.0 * (1.0 - normal_cdf(z.abs()));
+    Some((z, p_value))
+}
+
+/// Welch's t-test two-sided p-value for a difference in mean `times` between
+/// two samples, using the Welch-Satterthwaite degrees of freedom and the exact
+/// Student's t CDF (not a normal approximation — at the sample sizes this tool
+/// runs at, e.g. `--rounds 10`, the normal approximation understates p-values
+/// most when df is small, which is exactly when a few `MAX_TICKS` timeouts
+/// give one side a fat tail). Returns `None` when either sample has fewer than
+/// 2 points or the combined (unpooled) standard error is zero.
+fn welch_t_test(a: &[f64], b: &[f64]) -> Option<(f64, f64)> {
+    let n1 = a.len() as f64;
+    let n2 = b.len() as f64;
+    if n1 < 2.0 || n2 < 2.0 {
+        return None;
+    }
+    let mean = |xs: &[f64]| xs.iter().sum::<f64>() / xs.len() as f64;
+    let mean1 = mean(a);
+    let mean2 = mean(b);
+    let variance = |xs: &[f64], m: f64| xs.iter().map(|x| (x - m).powi(2)).sum::<f64>() / (xs.len() as f64 - 1.0);
+    let var1 = variance(a, mean1);
+    let var2 = variance(b, mean2);
+    let se2a = var1 / n1;
+    let se2b = var2 / n2;
+    let se = (se2a + se2b).sqrt();
+    if se == 0.0 {
+        return None;
+    }
+    let t = (mean2 - mean1) / se;
+    // Welch-Satterthwaite degrees of freedom.
+    let df = (se2a + se2b).powi(2) / (se2a.powi(2) / (n1 - 1.0) + se2b.powi(2) / (n2 - 1.0));
+    let p_value = 2.0 * (1.0 - t_cdf(t.abs(), df));
+    Some((t, p_value))
+}
+
+/// Student's t CDF via the regularized incomplete beta function: for t > 0,
+/// P(T <= t) = 1 - 0.5*I_x(df/2, 1/2) where x = df/(df+t^2).
+fn t_cdf(t: f64, df: f64) -> f64 {
+    let x = df / (df + t * t);
+    let ibeta = regularized_incomplete_beta(x, df / 2.0, 0.5);
+    if t >= 0.0 {
+        1.0 - 0.5 * ibeta
+    } else {
+        0.5 * ibeta
+    }
+}
+
+/// Lanczos approximation of the natural log of the gamma function, accurate to
+/// ~15 significant digits, used by `regularized_incomplete_beta`.
+fn log_gamma(x: f64) -> f64 {
+    const G: f64 = 7.0;
+    const COEFFS: [f64; 9] = [
+        0.99999999999980993,
+        676.5203681218851,
+        -1259.1392167224028,
+        771.32342877765313,
+        -176.61502916214059,
+        12.507343278686905,
+        -0.13857109526572012,
+        9.9843695780195716e-6,
+        1.5056327351493116e-7,
+    ];
+    if x < 0.5 {
+        return (std::f64::consts::PI / (std::f64::consts::PI * x).sin()).ln() - log_gamma(1.0 - x);
+    }
+    let x = x - 1.0;
+    let mut a = COEFFS[0];
+    let t = x + G + 0.5;
+    for (i, c) in COEFFS.iter().enumerate().skip(1) {
+        a += c / (x + i as f64);
+    }
+    0.5 * (2.0 * std::f64::consts::PI).ln() + (x + 0.5) * t.ln() - t + a.ln()
+}
+
+/// Continued-fraction expansion used by `regularized_incomplete_beta` (Numerical
+/// Recipes `betacf`).
+fn incomplete_beta_cf(x: f64, a: f64, b: f64) -> f64 {
+    const FPMIN: f64 = 1e-30;
+    let qab = a + b;
+    let qap = a + 1.0;
+    let qam = a - 1.0;
+    let mut c = 1.0;
+    let mut d = 1.0 - qab * x / qap;
+    if d.abs() < FPMIN {
+        d = FPMIN;
+    }
+    d = 1.0 / d;
+    let mut h = d;
+    for m in 1..200 {
+        let mf = m as f64;
+        let m2 = 2.0 * mf;
+        let aa = mf * (b - mf) * x / ((qam + m2) * (a + m2));
+        d = 1.0 + aa * d;
+        if d.abs() < FPMIN {
+            d = FPMIN;
+        }
+        c = 1.0 + aa / c;
+        if c.abs() < FPMIN {
+            c = FPMIN;
+        }
+        d = 1.0 / d;
+        h *= d * c;
+        let aa = -(a + mf) * (qab + mf) * x / ((a + m2) * (qap + m2));
+        d = 1.0 + aa * d;
+        if d.abs() < FPMIN {
+            d = FPMIN;
+        }
+        c = 1.0 + aa / c;
+        if c.abs() < FPMIN {
+            c = FPMIN;
+        }
+        d = 1.0 / d;
+        let del = d * c;
+        h *= del;
+        if (del - 1.0).abs() < 3e-12 {
+            break;
+        }
+    }
+    h
+}
+
+/// Regularized incomplete beta function I_x(a, b), used to get an exact
+/// Student's t CDF instead of a normal approximation.
+fn regularized_incomplete_beta(x: f64, a: f64, b: f64) -> f64 {
+    if x <= 0.0 {
+        return 0.0;
+    }
+    if x >= 1.0 {
+        return 1.0;
+    }
+    let bt = (log_gamma(a + b) - log_gamma(a) - log_gamma(b) + a * x.ln() + b * (1.0 - x).ln()).exp();
+    if x < (a + 1.0) / (a + b + 2.0) {
+        bt * incomplete_beta_cf(x, a, b) / a
+    } else {
+        1.0 - bt * incomplete_beta_cf(1.0 - x, b, a) / b
+    }
+}
+
+/// Wilson score 95% confidence interval for a win rate, which stays well-behaved
+/// even when the observed proportion is 0 or 1.
+fn wilson_interval(x: u32, n: u32) -> (f64, f64) {
+    let n = n as f64;
+    let p = x as f64 / n;
+    let z = 1.96;
+    let z2 = z * z;
+    let center = (p + z2 / (2.0 * n)) / (1.0 + z2 / n);
+    let half_width = (z / (1.0 + z2 / n)) * (p * (1.0 - p) / n + z2 / (4.0 * n * n)).sqrt();
+    (center - half_width, center + half_width)
+}
+
+fn avg_time(results: &Results) -> f64 {
+    results.times.iter().sum::<f64>() / results.times.len() as f64
+}
+
+/// Two-proportion z-test verdict and Wilson score win-rate CIs, computed once so
+/// that a JSON/CSV export carries the same significance numbers the pretty
+/// printer shows instead of forcing downstream dashboards to recompute them
+/// from the raw win counts.
+#[derive(Clone, Debug, Serialize)]
+struct Significance {
+    z: Option<f64>,
+    p_value: Option<f64>,
+    is_significant: bool,
+    baseline_win_rate_ci: (f64, f64),
+    new_win_rate_ci: (f64, f64),
+}
+
+fn significance(baseline: &Results, new: &Results) -> Significance {
+    let rounds = baseline.times.len() as u32;
+    let x1 = baseline.team0_wins.len() as u32;
+    let x2 = new.team0_wins.len() as u32;
+    let (z, p_value) = match two_proportion_z_test(x1, x2, rounds) {
+        Some((z, p_value)) => (Some(z), Some(p_value)),
+        None => (None, None),
+    };
+    Significance {
+        z,
+        p_value,
+        is_significant: matches!(p_value, Some(p) if p < 0.05),
+        baseline_win_rate_ci: wilson_interval(x1, rounds),
+        new_win_rate_ci: wilson_interval(x2, rounds),
+    }
+}
+
+#[derive(Clone, Serialize)]
 struct BenchmarkResults {
     scene: String,
     baseline: Results,
     new: Results,
+    baseline_timing: TimingStats,
+    new_timing: TimingStats,
+    significance: Significance,
+}
+
+impl BenchmarkResults {
+    fn new(scene: String, baseline: Results, new: Results) -> Self {
+        let baseline_timing = timing_stats(&baseline);
+        let new_timing = timing_stats(&new);
+        let significance = significance(&baseline, &new);
+        Self { scene, baseline, new, baseline_timing, new_timing, significance }
+    }
+
+    fn rounds(&self) -> u32 {
+        self.baseline.times.len() as u32
+    }
+
+    fn win_change(&self) -> i32 {
+        self.new.team0_wins.len() as i32 - self.baseline.team0_wins.len() as i32
+    }
+
+    fn time_change(&self) -> f64 {
+        avg_time(&self.new) - avg_time(&self.baseline)
+    }
+
+    /// Whether the win-rate difference between baseline and new is statistically
+    /// significant at p < 0.05, per the two-proportion z-test.
+    fn is_significant(&self) -> bool {
+        self.significance.is_significant
+    }
+
+    /// Whether the mean `score_time` difference between baseline and new is
+    /// statistically significant at p < 0.05, per Welch's t-test.
+    fn is_time_change_significant(&self) -> bool {
+        matches!(welch_t_test(&self.baseline.times, &self.new.times), Some((_, p)) if p < 0.05)
+    }
+
+    fn to_csv_row(&self) -> String {
+        let fmt_opt = |v: Option<f64>| v.map(|v| format!("{:.6}", v)).unwrap_or_else(|| "n/a".to_string());
+        format!(
+            "{},{},{},{},{},{:.6},{:.6},{},{},{},\
+             {:.6},{:.6},{:.6},{:.6},{:.6},{:.6},\
+             {:.6},{:.6},{:.6},{:.6},{:.6},{:.6},\
+             {},{},{},{:.6},{:.6},{:.6},{:.6}",
+            self.scene,
+            self.baseline.team0_wins.len(),
+            self.new.team0_wins.len(),
+            self.baseline.draws.len(),
+            self.new.draws.len(),
+            avg_time(&self.baseline),
+            avg_time(&self.new),
+            self.win_change(),
+            self.baseline.timeouts.len(),
+            self.new.timeouts.len(),
+            self.baseline_timing.min,
+            self.baseline_timing.median,
+            self.baseline_timing.p90,
+            self.baseline_timing.p95,
+            self.baseline_timing.max,
+            self.baseline_timing.stddev,
+            self.new_timing.min,
+            self.new_timing.median,
+            self.new_timing.p90,
+            self.new_timing.p95,
+            self.new_timing.max,
+            self.new_timing.stddev,
+            fmt_opt(self.significance.z),
+            fmt_opt(self.significance.p_value),
+            self.significance.is_significant,
+            self.significance.baseline_win_rate_ci.0,
+            self.significance.baseline_win_rate_ci.1,
+            self.significance.new_win_rate_ci.0,
+            self.significance.new_win_rate_ci.1,
+        )
+    }
 }
 
 impl Display for BenchmarkResults {
@@ -109,9 +522,28 @@ impl Display for BenchmarkResults {
             write!(f, "{BOLD}Win change{RESET} {BOLD}{BRIGHT_RED}{}{RESET}", win_change)
         }?;
         write!(f, " ({} -> {})\n", self.baseline.team0_wins.len(), self.new.team0_wins.len())?;
-        let avg_time = |results: &Results| -> f64 {
-            results.times.iter().sum::<f64>() / results.times.len() as f64
-        };
+
+        let (lo1, hi1) = self.significance.baseline_win_rate_ci;
+        let (lo2, hi2) = self.significance.new_win_rate_ci;
+        write!(
+            f,
+            "{BOLD}Win rate 95% CI{RESET} [{:.1}%, {:.1}%] -> [{:.1}%, {:.1}%]\n",
+            lo1 * 100.0, hi1 * 100.0, lo2 * 100.0, hi2 * 100.0
+        )?;
+        match (self.significance.z, self.significance.p_value) {
+            (Some(z), Some(p_value)) => {
+                let (color, verdict) = if p_value < 0.05 {
+                    if win_change > 0 { (BRIGHT_GREEN, "significant") } else { (BRIGHT_RED, "significant") }
+                } else {
+                    (RESET, "not significant")
+                };
+                write!(f, "{BOLD}Significance{RESET} z={:.3} p={:.4} {color}{verdict}{RESET}\n", z, p_value)?;
+            }
+            _ => {
+                write!(f, "{BOLD}Significance{RESET} n/a (no variance in win rate)\n")?;
+            }
+        }
+
         let baseline_avg_time = avg_time(&self.baseline);
         let new_avg_time = avg_time(&self.new);
         if new_avg_time < baseline_avg_time {
@@ -121,24 +553,127 @@ impl Display for BenchmarkResults {
         } else {
             write!(f, "{BOLD}Avg time change{RESET} {BOLD}{BRIGHT_RED}{:.3}{RESET}", new_avg_time - baseline_avg_time)
         }?;
-        write!(f, " ({:.3} -> {:.3})", baseline_avg_time, new_avg_time)?;
+        write!(f, " ({:.3} -> {:.3})\n", baseline_avg_time, new_avg_time)?;
+
+        let fmt_timing = |label: &str, results: &Results, timing: &TimingStats| -> String {
+            format!(
+                "{BOLD}{label}{RESET} min {:.3} median {:.3} p90 {:.3} p95 {:.3} max {:.3} stddev {:.3} timeouts {}/{}",
+                timing.min, timing.median, timing.p90, timing.p95, timing.max, timing.stddev,
+                results.timeouts.len(), results.times.len()
+            )
+        };
+        write!(f, "{}\n", fmt_timing("Baseline timing", &self.baseline, &self.baseline_timing))?;
+        write!(f, "{}", fmt_timing("New timing", &self.new, &self.new_timing))?;
         Ok(())
     }
 }
 
-fn run_simulations_packaged(args: &Arguments, scene: &str, player: &AI, enemy: &AI) -> Result<Results, String> {
-    info!("Running Scene: {scene}");
-    scenario::load_safe(scene).expect(&format!("Unknown scenario {scene}"));
-    info!("Compiling AIs");
+/// Wraps `oort_compiler::Compiler` with an optional content-addressed disk cache:
+/// the AI source is hashed to a filename under `cache_dir`, so repeated benchmark
+/// runs and duplicated builtin enemies skip recompilation entirely.
+struct CachedCompiler {
+    compiler: oort_compiler::Compiler,
+    cache_dir: Option<PathBuf>,
+}
 
-    info!("Running simulations");
-    let results = run_simulations(scene, vec![player.compiled_code.clone(), enemy.compiled_code.clone()], args.rounds)?;
+impl CachedCompiler {
+    fn new(cache_dir: Option<PathBuf>) -> Self {
+        if let Some(dir) = &cache_dir {
+            if let Err(e) = std::fs::create_dir_all(dir) {
+                warn!("Failed to create wasm cache dir {dir:?}: {e}");
+            }
+        }
+        Self { compiler: oort_compiler::Compiler::new(), cache_dir }
+    }
 
-    Ok(results)
+    fn cache_path(&self, src: &str) -> Option<PathBuf> {
+        self.cache_dir.as_ref().map(|dir| {
+            let hash = blake3::hash(src.as_bytes());
+            dir.join(format!("{}.wasm", hash.to_hex()))
+        })
+    }
+
+    fn compile(&mut self, src: &str) -> Result<Vec<u8>, String> {
+        if let Some(path) = self.cache_path(src) {
+            if let Ok(wasm) = std::fs::read(&path) {
+                debug!("Wasm cache hit: {path:?}");
+                return Ok(wasm);
+            }
+            let wasm = self.compiler.compile(src)?;
+            self.write_cache_entry(&path, &wasm);
+            Ok(wasm)
+        } else {
+            self.compiler.compile(src)
+        }
+    }
+
+    /// Writes to a sibling temp file and renames into place, so a concurrent
+    /// bencher invocation can never observe a partially-written cache entry.
+    fn write_cache_entry(&self, path: &Path, wasm: &[u8]) {
+        let tmp_path = path.with_extension(format!("wasm.tmp.{}", std::process::id()));
+        if let Err(e) = std::fs::write(&tmp_path, wasm) {
+            warn!("Failed to write wasm cache entry {tmp_path:?}: {e}");
+            return;
+        }
+        if let Err(e) = std::fs::rename(&tmp_path, path) {
+            warn!("Failed to finalize wasm cache entry {path:?}: {e}");
+            let _ = std::fs::remove_file(&tmp_path);
+        }
+    }
+}
+
+fn parse_scene_listing(scene_listing: &str) -> std::io::Result<Vec<String>> {
+    if Path::new(scene_listing).is_file() {
+        let scene_file = std::fs::File::open(scene_listing)?;
+        Ok(std::io::BufReader::new(scene_file)
+            .lines()
+            .map(|line| line.unwrap())
+            .filter(|line| !line.starts_with('#'))
+            .map(|line| line.trim().to_string())
+            .collect())
+    } else {
+        Ok(scene_listing.split(',').map(|s| s.to_string()).collect())
+    }
+}
+
+const ELO_K: f64 = 32.0;
+const ELO_INITIAL: f64 = 1500.0;
+
+fn elo_expected(rating: f64, opponent: f64) -> f64 {
+    1.0 / (1.0 + 10f64.powf((opponent - rating) / 400.0))
+}
+
+fn elo_update(rating: f64, expected: f64, score: f64) -> f64 {
+    rating + ELO_K * (score - expected)
+}
+
+/// One unit of flattened tournament work: play a single seed of `scene` between
+/// AI `i` (team0) and AI `j` (team1). Every ordered pairing is independent, so
+/// this is driven through the same flattened rayon pool as the head-to-head path.
+#[derive(Clone, Copy)]
+struct TournamentWorkItem {
+    scene: usize,
+    i: usize,
+    j: usize,
+    seed: u32,
 }
 
-fn run_benchmark(args: Arguments, scene: String, enemy: Code, compiled_baseline: &AI, compiled_new: &AI) -> BenchmarkResults {
-    let mut compiler = oort_compiler::Compiler::new();
+#[derive(Clone, Serialize)]
+struct EloEntry {
+    name: String,
+    elo: f64,
+}
+
+#[derive(Clone, Serialize)]
+struct TournamentReport {
+    leaderboard: Vec<EloEntry>,
+    ai_names: Vec<String>,
+    /// `win_rate_matrix[i][j]` is AI `i`'s win rate as team0 against AI `j` as
+    /// team1 (draws count as half a win); the diagonal is unplayed and left 0.
+    win_rate_matrix: Vec<Vec<f64>>,
+}
+
+fn compile_enemy(compiler: &mut CachedCompiler, enemy: Code) -> AI {
     let src = match enemy {
         Code::Rust(src) => src,
         Code::Builtin(name) => {
@@ -153,53 +688,24 @@ fn run_benchmark(args: Arguments, scene: String, enemy: Code, compiled_baseline:
         _ => panic!("Invalid code type"),
     };
     let wasm = compiler.compile(&src).unwrap();
-    let enemy_ai = AI {
+    AI {
         name: "Enemy".to_string(),
         source_code: src,
         compiled_code: Code::Wasm(wasm),
-    };
-    let res = vec![compiled_baseline, compiled_new].into_par_iter().map(|p| {
-        run_simulations_packaged(&args, &scene, p, &enemy_ai).unwrap()
-    }).collect::<Vec<Results>>();
-    let base_results = res[0].clone();
-    let new_results = res[1].clone();
-    BenchmarkResults {
-        scene,
-        baseline: base_results,
-        new: new_results,
-
     }
 }
 
-#[tokio::main]
-async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-    env_logger::Builder::from_env(env_logger::Env::default().filter_or("benchmark", "warn"))
-        .init();
-
-    let args = Arguments::parse();
-
+async fn run_benchmark_command(args: BenchmarkArgs) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
     let mut scene_mapping = HashMap::new();
-    let scenes: Vec<String> = if Path::new(&args.scene_listing).is_file() {
-        let scene_file = std::fs::File::open(&args.scene_listing)?;
-        std::io::BufReader::new(scene_file)
-            .lines()
-            .map(|line| line.unwrap())
-            .filter(|line| !line.starts_with('#'))
-            .map(|line| line.trim().to_string())
-            .collect()
-    } else {
-        args.scene_listing
-            .split(',')
-            .map(|s| s.to_string())
-            .collect()
-    };
-    for scene in scenes {
-        let scenario = scenario::load_safe(&scene).expect(&format!("Unknown scenario {scene}"));
+    let scenes = parse_scene_listing(&args.scene_listing)?;
+    for scene in &scenes {
+        let scenario = scenario::load_safe(scene).expect(&format!("Unknown scenario {scene}"));
         scene_mapping.insert(scene.to_string(), scenario.initial_code()[1].clone());
     }
 
+    let mut compiler = CachedCompiler::new(args.wasm_cache.clone());
+
     println!("{BRIGHT_BLUE}Compiling inputted AIs{RESET}");
-    let mut compiler = oort_compiler::Compiler::new();
     let src = std::fs::read_to_string(&args.baseline_shortcode).unwrap();
     let wasm = compiler.compile(&src).unwrap();
     let baseline = AI {
@@ -214,25 +720,228 @@ async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
         source_code: src,
         compiled_code: Code::Wasm(wasm),
     };
+    let players = [&baseline, &new];
 
     let converted_scene_mapping: Vec<(String, Code)> = scene_mapping.iter().map(|(k, v)| (k.clone(), v.clone())).collect();
+    let scene_names: Vec<String> = converted_scene_mapping.iter().map(|(scene, _)| scene.clone()).collect();
+
+    println!("{BRIGHT_BLUE}Compiling scene enemies{RESET}");
+    let enemies: Vec<AI> = converted_scene_mapping.into_iter().map(|(_, enemy)| compile_enemy(&mut compiler, enemy)).collect();
+
+    println!("{BRIGHT_BLUE}Building thread pool with {} threads{RESET}", args.threads);
+    let pool = rayon::ThreadPoolBuilder::new().num_threads(args.threads).build()?;
+
+    let work_items: Vec<WorkItem> = (0..scene_names.len())
+        .flat_map(|scene| (0..players.len()).map(move |which| (scene, which)))
+        .flat_map(|(scene, which)| (0..args.rounds).map(move |seed| WorkItem { scene, which, seed }))
+        .collect();
+
     println!("{BRIGHT_BLUE}Running Benchmarks{RESET}");
     let completed_num = AtomicUsize::new(0);
-    let total = converted_scene_mapping.len();
-    let results = converted_scene_mapping.par_iter().map(|(scene, enemy)| {
-        let args = args.clone();
-        let results = run_benchmark(args, scene.clone(), enemy.clone(), &baseline, &new);
-        completed_num.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
-        let completed = completed_num.load(std::sync::atomic::Ordering::SeqCst);
-        println!("{BRIGHT_BLUE}Completed {completed}/{total} benchmarks{RESET}", completed = completed, total = total);
-        results
-    }).collect::<Vec<BenchmarkResults>>();
-    println!("{BRIGHT_BLUE}Results{RESET}");
-
-    for result in results {
-        println!("{BRIGHT_BLUE}Results for {BOLD}{scene}{OFF_BOLD}", scene = result.scene);
-        println!("{}", result);
+    let total = work_items.len();
+    let raw_results: Vec<(WorkItem, Status, f64)> = pool.install(|| {
+        work_items.par_iter().map(|item| {
+            let codes = vec![players[item.which].compiled_code.clone(), enemies[item.scene].compiled_code.clone()];
+            let (status, time) = run_simulation(&scene_names[item.scene], item.seed, codes);
+            let completed = completed_num.fetch_add(1, std::sync::atomic::Ordering::SeqCst) + 1;
+            if completed % scene_names.len().max(1) == 0 || completed == total {
+                println!("{BRIGHT_BLUE}Completed {completed}/{total} simulations{RESET}", completed = completed, total = total);
+            }
+            (*item, status, time)
+        }).collect()
+    });
+
+    let mut benchmark_results: Vec<BenchmarkResults> = Vec::with_capacity(scene_names.len());
+    for (scene_idx, scene) in scene_names.iter().enumerate() {
+        let mut per_which: [Vec<(u32, Status, f64)>; 2] = [Vec::new(), Vec::new()];
+        for (item, status, time) in &raw_results {
+            if item.scene == scene_idx {
+                per_which[item.which].push((item.seed, *status, *time));
+            }
+        }
+        let baseline_results = aggregate_results(per_which[0].clone())?;
+        let new_results = aggregate_results(per_which[1].clone())?;
+        benchmark_results.push(BenchmarkResults::new(scene.clone(), baseline_results, new_results));
+    }
+
+    match args.format {
+        OutputFormat::Pretty => {
+            println!("{BRIGHT_BLUE}Results{RESET}");
+            for result in &benchmark_results {
+                println!("{BRIGHT_BLUE}Results for {BOLD}{scene}{OFF_BOLD}", scene = result.scene);
+                println!("{}", result);
+            }
+        }
+        OutputFormat::Json => {
+            println!("{}", serde_json::to_string_pretty(&benchmark_results)?);
+        }
+        OutputFormat::Csv => {
+            println!(
+                "scene,baseline_wins,new_wins,baseline_draws,new_draws,baseline_mean_time,new_mean_time,win_delta,\
+                 baseline_timeouts,new_timeouts,\
+                 baseline_min_time,baseline_median_time,baseline_p90_time,baseline_p95_time,baseline_max_time,baseline_stddev_time,\
+                 new_min_time,new_median_time,new_p90_time,new_p95_time,new_max_time,new_stddev_time,\
+                 z,p_value,is_significant,baseline_win_rate_ci_low,baseline_win_rate_ci_high,new_win_rate_ci_low,new_win_rate_ci_high"
+            );
+            for result in &benchmark_results {
+                println!("{}", result.to_csv_row());
+            }
+        }
+    }
+
+    if args.fail_on_regression {
+        let offenders: Vec<&BenchmarkResults> = benchmark_results.iter()
+            .filter(|result| {
+                let win_regressed = result.is_significant() && result.win_change() < -args.min_win_delta;
+                let time_regressed = result.is_time_change_significant() && result.time_change() > args.max_time_regression;
+                win_regressed || time_regressed
+            })
+            .collect();
+        if !offenders.is_empty() {
+            eprintln!("{BOLD}{BRIGHT_RED}Regression gate failed:{RESET}");
+            for result in &offenders {
+                eprintln!(
+                    "  {}: win change {} (min allowed -{}), time change {:.3} (max allowed {:.3})",
+                    result.scene, result.win_change(), args.min_win_delta, result.time_change(), args.max_time_regression
+                );
+            }
+            std::process::exit(1);
+        }
+    }
+
+    Ok(())
+}
+
+async fn run_tournament_command(args: TournamentArgs) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let scenes = parse_scene_listing(&args.scene_listing)?;
+    for scene in &scenes {
+        scenario::load_safe(scene).expect(&format!("Unknown scenario {scene}"));
+    }
+
+    let mut compiler = CachedCompiler::new(args.wasm_cache.clone());
+
+    println!("{BRIGHT_BLUE}Compiling tournament AIs{RESET}");
+    let ais: Vec<AI> = args.shortcodes.iter().map(|shortcode| {
+        let src = std::fs::read_to_string(shortcode).unwrap();
+        let wasm = compiler.compile(&src).unwrap();
+        AI { name: shortcode.clone(), source_code: src, compiled_code: Code::Wasm(wasm) }
+    }).collect();
+    let n = ais.len();
+
+    println!("{BRIGHT_BLUE}Building thread pool with {} threads{RESET}", args.threads);
+    let pool = rayon::ThreadPoolBuilder::new().num_threads(args.threads).build()?;
+
+    let work_items: Vec<TournamentWorkItem> = (0..scenes.len())
+        .flat_map(|scene| (0..n).flat_map(move |i| (0..n).filter(move |&j| j != i).map(move |j| (scene, i, j))))
+        .flat_map(|(scene, i, j)| (0..args.rounds).map(move |seed| TournamentWorkItem { scene, i, j, seed }))
+        .collect();
+
+    println!("{BRIGHT_BLUE}Running Tournament{RESET}");
+    let completed_num = AtomicUsize::new(0);
+    let total = work_items.len();
+    let raw_results: Vec<(TournamentWorkItem, Status)> = pool.install(|| {
+        work_items.par_iter().map(|item| {
+            let codes = vec![ais[item.i].compiled_code.clone(), ais[item.j].compiled_code.clone()];
+            let (status, _time) = run_simulation(&scenes[item.scene], item.seed, codes);
+            let completed = completed_num.fetch_add(1, std::sync::atomic::Ordering::SeqCst) + 1;
+            if completed % scenes.len().max(1) == 0 || completed == total {
+                println!("{BRIGHT_BLUE}Completed {completed}/{total} simulations{RESET}", completed = completed, total = total);
+            }
+            (*item, status)
+        }).collect()
+    });
+
+    // `par_iter().collect()` preserves the input order, and `work_items` was built in
+    // (scene, i, j, seed) order, so replaying `raw_results` in order plays every game
+    // deterministically for the Elo update.
+    let mut ratings = vec![ELO_INITIAL; n];
+    let mut win_counts = vec![vec![0u32; n]; n];
+    let mut draw_counts = vec![vec![0u32; n]; n];
+    let mut total_counts = vec![vec![0u32; n]; n];
+    for (item, status) in &raw_results {
+        total_counts[item.i][item.j] += 1;
+        let score_i = match status {
+            Status::Victory { team: 0 } => {
+                win_counts[item.i][item.j] += 1;
+                1.0
+            }
+            Status::Victory { team: 1 } | Status::Failed => 0.0,
+            Status::Draw => {
+                draw_counts[item.i][item.j] += 1;
+                0.5
+            }
+            Status::Victory { team: s } => return Err(format!("Invalid team {}", s).into()),
+            Status::Running => return Err("Scenario should not be running".into()),
+        };
+        let expected_i = elo_expected(ratings[item.i], ratings[item.j]);
+        ratings[item.i] = elo_update(ratings[item.i], expected_i, score_i);
+        ratings[item.j] = elo_update(ratings[item.j], 1.0 - expected_i, 1.0 - score_i);
+    }
+
+    let win_rate_matrix: Vec<Vec<f64>> = (0..n).map(|i| {
+        (0..n).map(|j| {
+            if total_counts[i][j] == 0 {
+                0.0
+            } else {
+                (win_counts[i][j] as f64 + 0.5 * draw_counts[i][j] as f64) / total_counts[i][j] as f64
+            }
+        }).collect()
+    }).collect();
+
+    let mut ranking: Vec<usize> = (0..n).collect();
+    ranking.sort_by(|&a, &b| ratings[b].partial_cmp(&ratings[a]).unwrap());
+    let leaderboard: Vec<EloEntry> = ranking.iter().map(|&i| EloEntry { name: ais[i].name.clone(), elo: ratings[i] }).collect();
+    let report = TournamentReport {
+        leaderboard,
+        ai_names: ais.iter().map(|ai| ai.name.clone()).collect(),
+        win_rate_matrix,
+    };
+
+    match args.format {
+        OutputFormat::Pretty => {
+            println!("{BRIGHT_BLUE}Leaderboard{RESET}");
+            for (rank, entry) in report.leaderboard.iter().enumerate() {
+                println!("{BOLD}{}{OFF_BOLD} {} {:.1}", rank + 1, entry.name, entry.elo);
+            }
+            println!("{BRIGHT_BLUE}Win-rate matrix (row vs column, row as team0){RESET}");
+            print!("{:<24}", "");
+            for name in &report.ai_names {
+                print!("{:>10.10}", name);
+            }
+            println!();
+            for (i, name) in report.ai_names.iter().enumerate() {
+                print!("{:<24.24}", name);
+                for j in 0..n {
+                    if i == j {
+                        print!("{:>10}", "-");
+                    } else {
+                        print!("{:>10.1}", report.win_rate_matrix[i][j] * 100.0);
+                    }
+                }
+                println!();
+            }
+        }
+        OutputFormat::Json => {
+            println!("{}", serde_json::to_string_pretty(&report)?);
+        }
+        OutputFormat::Csv => {
+            println!("name,elo");
+            for entry in &report.leaderboard {
+                println!("{},{:.1}", entry.name, entry.elo);
+            }
+        }
     }
 
     Ok(())
 }
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    env_logger::Builder::from_env(env_logger::Env::default().filter_or("benchmark", "warn"))
+        .init();
+
+    match Cli::parse().command {
+        Command::Benchmark(args) => run_benchmark_command(args).await,
+        Command::Tournament(args) => run_tournament_command(args).await,
+    }
+}